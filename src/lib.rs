@@ -2,8 +2,6 @@
 pub struct Snowflake {
     datacenter_id: u64,
     worker_id: u64,
-    sequence: u64,
-    lock: std::sync::Mutex<()>,
 
     epoch: i64,
     max_sequence: u64,
@@ -12,10 +10,23 @@ pub struct Snowflake {
     datacenter_id_shift: u64,
     timestamp_left_shift: u64,
 
+    // Packs `last_timestamp` and `sequence` into a single word so
+    // `generate_id` can update both atomically without a lock.
+    state: std::sync::atomic::AtomicU64,
+
+    clock_rollback_policy: ClockRollbackPolicy,
+    clock_rollback_tolerance_ms: i64,
+
+    #[cfg(feature = "legacy-mutex")]
+    sequence: u64,
+    #[cfg(feature = "legacy-mutex")]
     last_timestamp: i64,
+    #[cfg(feature = "legacy-mutex")]
+    lock: std::sync::Mutex<()>,
 }
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SnowflakeBuilder {
     datacenter_id: u64,
     worker_id: u64,
@@ -33,12 +44,163 @@ pub struct SnowflakeBuilder {
     worker_id_shift: u64,
     datacenter_id_shift: u64,
     timestamp_left_shift: u64,
+
+    clock_rollback_policy: ClockRollbackPolicy,
+    clock_rollback_tolerance_ms: i64,
+}
+
+/// How [`Snowflake::generate_id`] should react when the system clock is
+/// observed to have moved backwards (NTP adjustment, VM migration, leap
+/// second smearing, ...).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ClockRollbackPolicy {
+    /// Panic immediately. This is the original behavior.
+    Panic,
+    /// Return [`SnowflakeError::ClockMovedBackwards`] instead of panicking.
+    ReturnError,
+    /// Spin until the clock catches back up to `last_timestamp`, as long as
+    /// the rollback is within [`with_clock_rollback_tolerance_ms`]; beyond
+    /// that, return [`SnowflakeError::ClockMovedBackwards`].
+    ///
+    /// [`with_clock_rollback_tolerance_ms`]: SnowflakeBuilder::with_clock_rollback_tolerance_ms
+    WaitUntilCaughtUp,
 }
 
 #[derive(Debug)]
 pub enum SnowflakeError {
     DatacenterIDOutOfRange((u64, u64, u32)), // config, max, recommended
     WorkerIDOutOfRange((u64, u64, u32)),
+    ClockMovedBackwards { last: i64, now: i64, delta_ms: i64 },
+    BitLayoutOverflow { total_bits: u64, max_allowed: u64 },
+    TimestampWouldExceed {
+        total_bits: u64,
+        timestamp_bits: u64,
+        years: f64,
+    },
+}
+
+// The hard cap on `datacenter_id_bits + worker_id_bits + sequence_bits`:
+// beyond this, fewer than one timestamp bit would remain within the 63 bits
+// available to an `i64`-safe (sign bit always zero) generated ID.
+const MAX_LOWER_BITS: u64 = 62;
+
+// The recommended cap on the same sum, matching the default 5/5/12 layout
+// and leaving 41 timestamp bits (~69 years from the epoch).
+const RECOMMENDED_MAX_LOWER_BITS: u64 = 22;
+
+fn years_representable(timestamp_bits: u64) -> f64 {
+    let max_timestamp_ms = (1u64 << timestamp_bits) - 1;
+    return max_timestamp_ms as f64 / (1000.0 * 60.0 * 60.0 * 24.0 * 365.25);
+}
+
+/// The component fields recovered from a previously generated Snowflake ID.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodedSnowflake {
+    pub timestamp_ms: i64,
+    pub datacenter_id: u64,
+    pub worker_id: u64,
+    pub sequence: u64,
+}
+
+// `Snowflake` holds an `AtomicU64` (and, behind `legacy-mutex`, a `Mutex`),
+// neither of which is `Serialize`/`Deserialize`, so it can't be derived
+// directly. Instead it (de)serializes through this plain shadow of its
+// configuration plus the current generator state, and rebuilds the derived
+// shifts/masks on the way back in.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SnowflakeSerde {
+    datacenter_id: u64,
+    worker_id: u64,
+
+    epoch: i64,
+    datacenter_id_bits: u64,
+    worker_id_bits: u64,
+    sequence_bits: u64,
+
+    clock_rollback_policy: ClockRollbackPolicy,
+    clock_rollback_tolerance_ms: i64,
+
+    last_timestamp: i64,
+    sequence: u64,
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Snowflake {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let state = self.state.load(std::sync::atomic::Ordering::SeqCst);
+        let (last_timestamp, sequence) = self.unpack_state(state);
+
+        let shadow = SnowflakeSerde {
+            datacenter_id: self.datacenter_id,
+            worker_id: self.worker_id,
+
+            epoch: self.epoch,
+            datacenter_id_bits: self.timestamp_left_shift - self.datacenter_id_shift,
+            worker_id_bits: self.datacenter_id_shift - self.worker_id_shift,
+            sequence_bits: self.worker_id_shift,
+
+            clock_rollback_policy: self.clock_rollback_policy,
+            clock_rollback_tolerance_ms: self.clock_rollback_tolerance_ms,
+
+            last_timestamp,
+            sequence,
+        };
+
+        serde::Serialize::serialize(&shadow, serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Snowflake {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let shadow: SnowflakeSerde = serde::Deserialize::deserialize(deserializer)?;
+
+        let max_sequence = (-1i64 ^ (-1i64 << shadow.sequence_bits)) as u64;
+
+        let worker_id_shift = shadow.sequence_bits;
+        let datacenter_id_shift = shadow.sequence_bits + shadow.worker_id_bits;
+        let timestamp_left_shift =
+            shadow.sequence_bits + shadow.worker_id_bits + shadow.datacenter_id_bits;
+
+        let snowflake = Snowflake {
+            datacenter_id: shadow.datacenter_id,
+            worker_id: shadow.worker_id,
+
+            epoch: shadow.epoch,
+            max_sequence,
+
+            worker_id_shift,
+            datacenter_id_shift,
+            timestamp_left_shift,
+
+            state: std::sync::atomic::AtomicU64::new(0),
+
+            clock_rollback_policy: shadow.clock_rollback_policy,
+            clock_rollback_tolerance_ms: shadow.clock_rollback_tolerance_ms,
+
+            #[cfg(feature = "legacy-mutex")]
+            sequence: shadow.sequence,
+            #[cfg(feature = "legacy-mutex")]
+            last_timestamp: shadow.last_timestamp,
+            #[cfg(feature = "legacy-mutex")]
+            lock: std::sync::Mutex::new(()),
+        };
+
+        let state = snowflake.pack_state(shadow.last_timestamp, shadow.sequence);
+        snowflake
+            .state
+            .store(state, std::sync::atomic::Ordering::SeqCst);
+
+        Ok(snowflake)
+    }
 }
 
 impl std::fmt::Display for SnowflakeError {
@@ -54,6 +216,22 @@ impl std::fmt::Display for SnowflakeError {
                 "worker_id ({}) out of range ({}), recommend using {} bits",
                 msg.0, msg.1, msg.2
             ),
+            SnowflakeError::ClockMovedBackwards { last, now, delta_ms } => write!(
+                f,
+                "clock moved backwards: last timestamp was {last}, now is {now} ({delta_ms}ms behind)"
+            ),
+            SnowflakeError::BitLayoutOverflow { total_bits, max_allowed } => write!(
+                f,
+                "datacenter_id_bits + worker_id_bits + sequence_bits ({total_bits}) leaves no room for a timestamp within 63 bits, recommend at most {max_allowed} bits total"
+            ),
+            SnowflakeError::TimestampWouldExceed {
+                total_bits,
+                timestamp_bits,
+                years,
+            } => write!(
+                f,
+                "datacenter_id_bits + worker_id_bits + sequence_bits ({total_bits}) leaves only {timestamp_bits} timestamp bits (~{years:.1} years), recommend keeping the total at or under {RECOMMENDED_MAX_LOWER_BITS} bits"
+            ),
         }
     }
 }
@@ -86,6 +264,9 @@ impl SnowflakeBuilder {
             worker_id_shift: sequence_bits,
             datacenter_id_shift: sequence_bits + worker_id_bits,
             timestamp_left_shift: sequence_bits + worker_id_bits + datacenter_id_bits,
+
+            clock_rollback_policy: ClockRollbackPolicy::Panic,
+            clock_rollback_tolerance_ms: 10,
         }
     }
 
@@ -110,6 +291,22 @@ impl SnowflakeBuilder {
         return self;
     }
 
+    /// Sets the policy for handling a backwards-moving system clock.
+    ///
+    /// Defaults to [`ClockRollbackPolicy::Panic`].
+    pub fn with_clock_rollback_policy(mut self, policy: ClockRollbackPolicy) -> Self {
+        self.clock_rollback_policy = policy;
+        return self;
+    }
+
+    /// Sets how many milliseconds of clock rollback
+    /// [`ClockRollbackPolicy::WaitUntilCaughtUp`] will spin through before
+    /// giving up and returning an error. Defaults to 10ms.
+    pub fn with_clock_rollback_tolerance_ms(mut self, tolerance_ms: i64) -> Self {
+        self.clock_rollback_tolerance_ms = tolerance_ms;
+        return self;
+    }
+
     /// Builds a new Snowflake instance.
     ///
     /// # Errors
@@ -144,11 +341,27 @@ impl SnowflakeBuilder {
             )));
         }
 
+        let lower_bits_total = self.datacenter_id_bits + self.worker_id_bits + self.sequence_bits;
+
+        if lower_bits_total > MAX_LOWER_BITS {
+            return Err(SnowflakeError::BitLayoutOverflow {
+                total_bits: lower_bits_total,
+                max_allowed: MAX_LOWER_BITS,
+            });
+        }
+
+        if lower_bits_total > RECOMMENDED_MAX_LOWER_BITS {
+            let timestamp_bits = 63 - lower_bits_total;
+            return Err(SnowflakeError::TimestampWouldExceed {
+                total_bits: lower_bits_total,
+                timestamp_bits,
+                years: years_representable(timestamp_bits),
+            });
+        }
+
         let snowflake = Snowflake {
             datacenter_id: self.datacenter_id,
             worker_id: self.worker_id,
-            sequence: self.sequence,
-            lock: std::sync::Mutex::new(()),
 
             epoch: self.epoch,
             max_sequence: self.max_sequence,
@@ -157,7 +370,17 @@ impl SnowflakeBuilder {
             datacenter_id_shift: self.datacenter_id_shift,
             timestamp_left_shift: self.timestamp_left_shift,
 
+            state: std::sync::atomic::AtomicU64::new(0),
+
+            clock_rollback_policy: self.clock_rollback_policy,
+            clock_rollback_tolerance_ms: self.clock_rollback_tolerance_ms,
+
+            #[cfg(feature = "legacy-mutex")]
+            sequence: self.sequence,
+            #[cfg(feature = "legacy-mutex")]
             last_timestamp: -1,
+            #[cfg(feature = "legacy-mutex")]
+            lock: std::sync::Mutex::new(()),
         };
 
         return Ok(snowflake);
@@ -185,13 +408,126 @@ impl Snowflake {
         return timestamp;
     }
 
+    fn pack_state(&self, last_timestamp: i64, sequence: u64) -> u64 {
+        return ((last_timestamp as u64) << self.worker_id_shift) | sequence;
+    }
+
+    fn unpack_state(&self, state: u64) -> (i64, u64) {
+        let last_timestamp = (state >> self.worker_id_shift) as i64;
+        let sequence = state & self.max_sequence;
+        return (last_timestamp, sequence);
+    }
+
+    // Applies `self.clock_rollback_policy` when `now` is behind
+    // `last_timestamp`, returning a timestamp that is safe to proceed with.
+    fn handle_clock_rollback(&self, last_timestamp: i64, now: i64) -> Result<i64, SnowflakeError> {
+        let delta_ms = last_timestamp - now;
+
+        match self.clock_rollback_policy {
+            ClockRollbackPolicy::Panic => {
+                panic!("Clock moved backwards. Refusing to generate id");
+            }
+            ClockRollbackPolicy::ReturnError => Err(SnowflakeError::ClockMovedBackwards {
+                last: last_timestamp,
+                now,
+                delta_ms,
+            }),
+            ClockRollbackPolicy::WaitUntilCaughtUp => {
+                if delta_ms > self.clock_rollback_tolerance_ms {
+                    return Err(SnowflakeError::ClockMovedBackwards {
+                        last: last_timestamp,
+                        now,
+                        delta_ms,
+                    });
+                }
+                Ok(self.wait_for_next_millis(last_timestamp))
+            }
+        }
+    }
+
     /// Generates a new Snowflake ID.
-    pub fn generate_id(&mut self) -> u64 {
+    ///
+    /// This is lock-free: `last_timestamp` and `sequence` are packed into a
+    /// single `AtomicU64` and advanced with a compare-and-swap loop, so a
+    /// `Snowflake` can be shared across threads (e.g. via `Arc`) without
+    /// external synchronization. Only the thread that wins the CAS emits
+    /// the ID assembled from the state it just installed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SnowflakeError::ClockMovedBackwards`] if the system clock
+    /// moves backwards and the builder's [`ClockRollbackPolicy`] is
+    /// [`ReturnError`] or the rollback exceeds [`WaitUntilCaughtUp`]'s
+    /// tolerance.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the system clock moves backwards and the builder's
+    /// [`ClockRollbackPolicy`] is [`Panic`] (the default).
+    ///
+    /// [`ReturnError`]: ClockRollbackPolicy::ReturnError
+    /// [`WaitUntilCaughtUp`]: ClockRollbackPolicy::WaitUntilCaughtUp
+    /// [`Panic`]: ClockRollbackPolicy::Panic
+    pub fn generate_id(&self) -> Result<u64, SnowflakeError> {
+        loop {
+            let state = self.state.load(std::sync::atomic::Ordering::SeqCst);
+            let (last_timestamp, sequence) = self.unpack_state(state);
+
+            let now = self.timestamp();
+            let now = if now < last_timestamp {
+                self.handle_clock_rollback(last_timestamp, now)?
+            } else {
+                now
+            };
+
+            let (timestamp, sequence) = if now > last_timestamp {
+                (now, 0)
+            } else {
+                let sequence = (sequence + 1) & self.max_sequence;
+                if sequence == 0 {
+                    (self.wait_for_next_millis(last_timestamp), 0)
+                } else {
+                    (last_timestamp, sequence)
+                }
+            };
+
+            let new_state = self.pack_state(timestamp, sequence);
+
+            if self
+                .state
+                .compare_exchange_weak(
+                    state,
+                    new_state,
+                    std::sync::atomic::Ordering::SeqCst,
+                    std::sync::atomic::Ordering::Relaxed,
+                )
+                .is_err()
+            {
+                continue;
+            }
+
+            let id = (((timestamp - self.epoch) as u64) << self.timestamp_left_shift)
+                | (self.datacenter_id << self.datacenter_id_shift)
+                | (self.worker_id << self.worker_id_shift)
+                | sequence;
+
+            return Ok(id);
+        }
+    }
+
+    /// Generates a new Snowflake ID using the original `Mutex`-guarded,
+    /// `&mut self` path.
+    ///
+    /// Kept for callers that already hold exclusive access to the
+    /// generator and prefer the pre-1.0 locking behavior over the
+    /// lock-free [`generate_id`](Self::generate_id).
+    #[cfg(feature = "legacy-mutex")]
+    pub fn generate_id_locked(&mut self) -> Result<u64, SnowflakeError> {
         let _lock = self.lock.lock().unwrap();
         let mut timestamp = self.timestamp();
 
         if timestamp < self.last_timestamp {
-            panic!("Clock moved backwards. Refusing to generate id");
+            timestamp = self.handle_clock_rollback(self.last_timestamp, timestamp)?;
         }
 
         if self.last_timestamp == timestamp {
@@ -210,7 +546,32 @@ impl Snowflake {
             | (self.worker_id << self.worker_id_shift)
             | self.sequence;
 
-        return id;
+        return Ok(id);
+    }
+
+    /// Decodes a previously generated ID back into its component fields.
+    ///
+    /// Uses this instance's configured shifts and masks, so an ID must be
+    /// decoded by a [`Snowflake`] built with the same bit layout that
+    /// generated it.
+    pub fn decode(&self, id: u64) -> DecodedSnowflake {
+        let worker_id_bits = self.datacenter_id_shift - self.worker_id_shift;
+        let datacenter_id_bits = self.timestamp_left_shift - self.datacenter_id_shift;
+
+        let worker_id_mask = (1u64 << worker_id_bits) - 1;
+        let datacenter_id_mask = (1u64 << datacenter_id_bits) - 1;
+
+        let timestamp_ms = ((id >> self.timestamp_left_shift) as i64) + self.epoch;
+        let datacenter_id = (id >> self.datacenter_id_shift) & datacenter_id_mask;
+        let worker_id = (id >> self.worker_id_shift) & worker_id_mask;
+        let sequence = id & self.max_sequence;
+
+        return DecodedSnowflake {
+            timestamp_ms,
+            datacenter_id,
+            worker_id,
+            sequence,
+        };
     }
 }
 
@@ -220,8 +581,35 @@ mod tests {
 
     #[test]
     fn test_generate_id() {
-        let mut snowflake = Snowflake::new(1, 1, 0).build().unwrap();
-        println!("{}", snowflake.generate_id());
+        let snowflake = Snowflake::new(1, 1, 0).build().unwrap();
+        println!("{}", snowflake.generate_id().unwrap());
+    }
+
+    #[test]
+    fn test_generate_id_unique_across_threads() {
+        use std::sync::Arc;
+
+        let snowflake = Arc::new(Snowflake::new(1, 1, 0).build().unwrap());
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let snowflake = Arc::clone(&snowflake);
+                std::thread::spawn(move || {
+                    (0..100)
+                        .map(|_| snowflake.generate_id().unwrap())
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        let mut ids: Vec<u64> = handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect();
+
+        let total = ids.len();
+        ids.sort_unstable();
+        ids.dedup();
+        assert_eq!(ids.len(), total);
     }
 
     #[test]
@@ -235,4 +623,65 @@ mod tests {
             format!("datacenter_id (256) out of range (31), recommend using 9 bits")
         );
     }
+
+    #[test]
+    fn test_decode() {
+        let snowflake = Snowflake::new(3, 7, 0).build().unwrap();
+        let id = snowflake.generate_id().unwrap();
+
+        let decoded = snowflake.decode(id);
+        assert_eq!(decoded.datacenter_id, 3);
+        assert_eq!(decoded.worker_id, 7);
+        assert_eq!(decoded.sequence, 0);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_roundtrip() {
+        let snowflake = Snowflake::new(3, 7, 0).build().unwrap();
+        snowflake.generate_id().unwrap();
+
+        let json = serde_json::to_string(&snowflake).unwrap();
+        let restored: Snowflake = serde_json::from_str(&json).unwrap();
+
+        let id = snowflake.generate_id().unwrap();
+        let restored_id = restored.generate_id().unwrap();
+        assert_eq!(snowflake.decode(id), restored.decode(restored_id));
+    }
+
+    #[test]
+    fn test_clock_rollback_returns_error() {
+        let snowflake = Snowflake::new(1, 1, 0)
+            .with_clock_rollback_policy(ClockRollbackPolicy::ReturnError)
+            .build()
+            .unwrap();
+
+        let future_state = snowflake.pack_state(snowflake.timestamp() + 10_000, 0);
+        snowflake
+            .state
+            .store(future_state, std::sync::atomic::Ordering::SeqCst);
+
+        let err = snowflake.generate_id().unwrap_err();
+        assert!(matches!(err, SnowflakeError::ClockMovedBackwards { .. }));
+    }
+
+    #[test]
+    fn test_bit_layout_overflow() {
+        let err = Snowflake::new(0, 0, 0)
+            .with_sequence_bits(63)
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, SnowflakeError::BitLayoutOverflow { .. }));
+    }
+
+    #[test]
+    fn test_timestamp_would_exceed() {
+        let err = Snowflake::new(0, 0, 0)
+            .with_datacenter_id_bits(10)
+            .with_worker_id_bits(10)
+            .with_sequence_bits(10)
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, SnowflakeError::TimestampWouldExceed { .. }));
+    }
 }